@@ -0,0 +1,165 @@
+use embedded_graphics::prelude::*;
+use embedded_graphics_core::primitives::Rectangle;
+use u8g2_fonts::FontRenderer;
+
+use crate::display::{
+    self, name_display, power_display, signal_display, FlushableDisplay, Theme,
+};
+use crate::{Percent, User};
+
+fn signal_rect() -> Rectangle {
+    Rectangle::new(Point::new(0, 0), Size::new(34, 14))
+}
+
+fn name_rect() -> Rectangle {
+    Rectangle::new(Point::new(0, 0), Size::new(128, 49))
+}
+
+fn ip_rect() -> Rectangle {
+    Rectangle::new(Point::new(0, 50), Size::new(128, 14))
+}
+
+fn full_screen_rect() -> Rectangle {
+    Rectangle::new(Point::new(0, 0), Size::new(128, 64))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct NameContent {
+    label: String,
+    talking: bool,
+    presence: Option<bool>,
+}
+
+/// Models the UI as a handful of named regions, each remembering the inputs
+/// it was last drawn with. A region is only redrawn when its inputs
+/// actually change, and the bounding boxes of whatever redrew get unioned
+/// into one dirty rect so `flush` (and in principle the I2C bus) only has
+/// to push the part of the screen that moved.
+pub struct Compositor {
+    signal: Option<Percent>,
+    name: Option<NameContent>,
+    ip: Option<std::net::IpAddr>,
+    power: Option<(u32, u32)>,
+    dirty: Option<Rectangle>,
+}
+
+impl Default for Compositor {
+    fn default() -> Self {
+        Compositor {
+            signal: None,
+            name: None,
+            ip: None,
+            power: None,
+            dirty: None,
+        }
+    }
+}
+
+impl Compositor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops every region's cached state so the next call to each region
+    /// redraws unconditionally — used after something else (the history
+    /// screen, the fatal-error overlay) has drawn over the whole screen.
+    pub fn invalidate(&mut self) {
+        self.signal = None;
+        self.name = None;
+        self.ip = None;
+        self.power = None;
+    }
+
+    fn mark_dirty(&mut self, area: Rectangle) {
+        self.dirty = Some(match self.dirty.take() {
+            Some(existing) => union_rect(existing, area),
+            None => area,
+        });
+    }
+
+    pub fn signal<D, C>(&mut self, display: &mut D, theme: &Theme<C>, font2: &FontRenderer, percent: Percent)
+    where
+        D: DrawTarget<Color = C> + Dimensions,
+        C: PixelColor,
+    {
+        if self.signal != Some(percent) {
+            signal_display(display, theme, font2, percent);
+            self.signal = Some(percent);
+            self.mark_dirty(signal_rect());
+        }
+    }
+
+    pub fn name<D, C>(
+        &mut self,
+        display: &mut D,
+        theme: &Theme<C>,
+        font1: &FontRenderer,
+        font2: &FontRenderer,
+        user: &User,
+        talking: bool,
+        presence: Option<bool>,
+    ) where
+        D: DrawTarget<Color = C> + Dimensions,
+        C: PixelColor,
+    {
+        let content = NameContent {
+            label: user.to_string(),
+            talking,
+            presence,
+        };
+        if self.name.as_ref() != Some(&content) {
+            name_display(display, theme, font1, font2, user, talking, presence);
+            self.name = Some(content);
+            self.mark_dirty(name_rect());
+        }
+    }
+
+    pub fn ip<D, C>(&mut self, display: &mut D, theme: &Theme<C>, font2: &FontRenderer, ip: std::net::IpAddr)
+    where
+        D: DrawTarget<Color = C> + Dimensions,
+        C: PixelColor,
+    {
+        if self.ip != Some(ip) {
+            display::ip_display(display, theme, font2, ip);
+            self.ip = Some(ip);
+            self.mark_dirty(ip_rect());
+        }
+    }
+
+    /// `held`/`max` are rounded to whole milliseconds so the countdown only
+    /// redraws on an actual tick rather than every float epsilon.
+    pub fn power<D, C>(&mut self, display: &mut D, theme: &Theme<C>, held: std::time::Duration, max: std::time::Duration)
+    where
+        D: DrawTarget<Color = C> + Dimensions,
+        C: PixelColor,
+    {
+        let key = (held.as_millis() as u32, max.as_millis() as u32);
+        if self.power != Some(key) {
+            power_display(display, theme, &held.as_secs_f32(), max.as_secs_f32());
+            self.power = Some(key);
+            // The power arc clears the whole screen, so the dirty rect has
+            // to cover it all rather than just the arc's own bounding box.
+            self.mark_dirty(full_screen_rect());
+        }
+    }
+
+    /// Pushes just the union of whatever regions redrew this tick, and
+    /// skips the bus write entirely when nothing did.
+    pub fn flush<D, C>(&mut self, display: &mut D)
+    where
+        D: FlushableDisplay<Color = C> + Dimensions,
+        C: PixelColor,
+    {
+        if let Some(area) = self.dirty.take() {
+            display.flush_area(area).ok();
+        }
+    }
+}
+
+fn union_rect(a: Rectangle, b: Rectangle) -> Rectangle {
+    let min_x = a.top_left.x.min(b.top_left.x);
+    let min_y = a.top_left.y.min(b.top_left.y);
+    let max_x = (a.top_left.x + a.size.width as i32).max(b.top_left.x + b.size.width as i32);
+    let max_y = (a.top_left.y + a.size.height as i32).max(b.top_left.y + b.size.height as i32);
+    Rectangle::new(Point::new(min_x, min_y), Size::new((max_x - min_x) as u32, (max_y - min_y) as u32))
+}
@@ -0,0 +1,85 @@
+use linux_embedded_hal::I2cdev;
+use ssd1306::{prelude::DisplayConfig, rotation::DisplayRotation, size::DisplaySize128x64, I2CDisplayInterface, Ssd1306};
+use u8g2_fonts::{fonts, FontRenderer};
+
+use crate::{display, storage, Error};
+
+const DB_PATH: &str = "beltpack.db";
+
+const CODE_I2C: &str = "10-0";
+const CODE_GPIO: &str = "10-1";
+const CODE_NETWORK: &str = "10-2";
+const CODE_CONFIG: &str = "11-0";
+const CODE_UNKNOWN: &str = "55-5";
+
+/// Installs a panic hook that turns an unrecoverable `.unwrap()` into a
+/// readable on-device diagnostic instead of a silent black-screen freeze.
+///
+/// The hook can't borrow the display handle `main` is using, so it re-opens
+/// `/dev/i2c-0` and builds a fresh SSD1306 handle of its own. This only
+/// covers the I2C backend; a panic while driving the SPI color panel still
+/// halts without an on-screen message.
+pub fn install() {
+    std::panic::set_hook(Box::new(|info| {
+        let message = panic_message(info);
+        let code = classify(&message);
+
+        storage::log_fatal(DB_PATH, code.to_string().as_str(), &message);
+
+        if let Ok(mut fallback) = open_fallback_display() {
+            let font2 = FontRenderer::new::<fonts::u8g2_font_8x13_mr>();
+            display::draw_fatal_screen(&mut fallback, &font2, &code, &message);
+        }
+
+        eprintln!("fatal: {} ({})", message, code);
+
+        // A panic on a spawned thread (e.g. one of input::spawn's per-button
+        // GPIO threads) would otherwise just drop that thread: its channel
+        // sender is one of several still alive, so the main loop keeps
+        // running and its next periodic refresh overwrites this screen,
+        // silently losing that button forever while looking recovered.
+        // Abort unconditionally so the fatal screen actually stays up.
+        std::process::abort();
+    }));
+}
+
+fn open_fallback_display() -> Result<display::MonoDisplay, ()> {
+    let i2c = I2cdev::new("/dev/i2c-0").map_err(|_| ())?;
+    let interface = I2CDisplayInterface::new(i2c);
+    let mut fallback = Ssd1306::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
+        .into_buffered_graphics_mode();
+    fallback.init().map_err(|_| ())?;
+    Ok(fallback)
+}
+
+fn panic_message(info: &std::panic::PanicInfo) -> String {
+    if let Some(s) = info.payload().downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = info.payload().downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Maps a panic message to one of a handful of stable device codes. This is
+/// a best-effort string match against the known `.unwrap()` failure sites
+/// (I2C open, GPIO line request, `nmcli`/`local_ip` lookups, config parse)
+/// rather than anything structured, since `std::panic::Location` only gives
+/// us file/line, not which subsystem failed.
+fn classify(message: &str) -> Error {
+    let lower = message.to_lowercase();
+    let code = if lower.contains("config") {
+        CODE_CONFIG
+    } else if lower.contains("i2c") {
+        CODE_I2C
+    } else if lower.contains("gpio") {
+        CODE_GPIO
+    } else if lower.contains("nmcli") || lower.contains("ip address") || lower.contains("local_ip")
+    {
+        CODE_NETWORK
+    } else {
+        CODE_UNKNOWN
+    };
+    Error::new(code).unwrap()
+}
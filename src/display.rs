@@ -0,0 +1,508 @@
+use std::path::Path;
+
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Rgb565},
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, PrimitiveStyleBuilder, Sector},
+};
+use embedded_graphics_core::primitives::Rectangle;
+use configparser::ini::Ini;
+use display_interface_spi::SPIInterface;
+use gpio_cdev::{Chip, LineRequestFlags};
+use ili9341::{DisplaySize240x320, Ili9341, Orientation};
+use linux_embedded_hal::{CdevPin, Delay, I2cdev, Spidev};
+use ssd1306::{
+    mode::BufferedGraphicsMode,
+    prelude::{DisplayConfig, I2CInterface},
+    rotation::DisplayRotation,
+    size::DisplaySize128x64,
+    I2CDisplayInterface, Ssd1306,
+};
+use u8g2_fonts::{
+    types::{FontColor, HorizontalAlignment, VerticalPosition},
+    FontRenderer,
+};
+
+use crate::{Error, Percent, User};
+
+/// The SSD1306 only hits the bus when told to via `flush()`, while the
+/// ILI9341 driver writes straight through its SPI interface as each primitive
+/// is drawn. This trait lets the main loop call one `flush_display()` after a
+/// frame regardless of which backend is in play.
+pub trait FlushableDisplay: DrawTarget {
+    fn flush_display(&mut self) -> Result<(), Self::Error>;
+
+    /// Push just the pixels inside `area`. Neither backend's driver exposes
+    /// a true partial-window write today, so the default still does a full
+    /// flush — the real savings from the dirty-region compositor come from
+    /// `Compositor::flush` skipping the call entirely when nothing changed.
+    fn flush_area(&mut self, _area: Rectangle) -> Result<(), Self::Error> {
+        self.flush_display()
+    }
+}
+
+impl FlushableDisplay for MonoDisplay {
+    fn flush_display(&mut self) -> Result<(), Self::Error> {
+        self.flush()
+    }
+}
+
+impl FlushableDisplay for ColorDisplay {
+    fn flush_display(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Foreground/background pair for a display backend. The drawing functions
+/// below never hardcode `BinaryColor::On`/`Off` directly so the same layout
+/// code can be reused on a color SPI panel.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme<C> {
+    fg: C,
+    bg: C,
+}
+
+impl<C: PixelColor + Copy> Theme<C> {
+    pub fn new(fg: C, bg: C) -> Self {
+        Theme { fg, bg }
+    }
+
+    pub fn fg(&self) -> C {
+        self.fg
+    }
+
+    pub fn bg(&self) -> C {
+        self.bg
+    }
+}
+
+impl Theme<BinaryColor> {
+    /// The theme the SSD1306 has always used: on-black monochrome text.
+    pub fn mono() -> Self {
+        Theme::new(BinaryColor::On, BinaryColor::Off)
+    }
+}
+
+impl Theme<Rgb565> {
+    /// Default theme for the color ILI9341 panel: white-on-black, matching
+    /// the SSD1306's look so the layouts read the same on either backend.
+    pub fn rgb565() -> Self {
+        Theme::new(Rgb565::WHITE, Rgb565::BLACK)
+    }
+}
+
+/// Which physical panel to drive, chosen by the `[display]` section of
+/// `config.ini`:
+///
+/// ```ini
+/// [display]
+/// backend = i2c-ssd1306   ; or spi-ili9341
+/// bus = /dev/i2c-0         ; or /dev/spidev0.0 for the SPI backend
+/// rotation = 0             ; 0, 90, 180 or 270
+/// size = 128x64            ; or 240x320 for the SPI backend; the only
+///                           ; size each backend is built for, see
+///                           ; `check_size_config`
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayBackend {
+    I2cSsd1306,
+    SpiIli9341,
+}
+
+impl DisplayBackend {
+    pub fn from_config(config: &Ini) -> Result<Self, String> {
+        match config
+            .get("display", "backend")
+            .unwrap_or_else(|| "i2c-ssd1306".to_string())
+            .as_str()
+        {
+            "i2c-ssd1306" => Ok(DisplayBackend::I2cSsd1306),
+            "spi-ili9341" => Ok(DisplayBackend::SpiIli9341),
+            other => Err(format!("Unknown [display] backend: {}", other)),
+        }
+    }
+}
+
+fn rotation_from_config(config: &Ini) -> DisplayRotation {
+    match config.getuint("display", "rotation").unwrap_or(Some(0)) {
+        Some(90) => DisplayRotation::Rotate90,
+        Some(180) => DisplayRotation::Rotate180,
+        Some(270) => DisplayRotation::Rotate270,
+        _ => DisplayRotation::Rotate0,
+    }
+}
+
+fn orientation_from_config(config: &Ini) -> Orientation {
+    match config.getuint("display", "rotation").unwrap_or(Some(0)) {
+        Some(90) => Orientation::LandscapeFlipped,
+        Some(180) => Orientation::PortraitFlipped,
+        Some(270) => Orientation::Landscape,
+        _ => Orientation::Portrait,
+    }
+}
+
+/// `Ssd1306`/`Ili9341` are generic over panel size at compile time (it's
+/// baked into `MonoDisplay`/`ColorDisplay` via `DisplaySize128x64`/
+/// `DisplaySize240x320`), so `[display] size` can't actually select a
+/// different `DisplaySize*` here. Rather than read and silently drop the
+/// key, fail loudly at startup if it asks for anything but the one size
+/// each backend is built for.
+fn check_size_config(config: &Ini, supported: &str) {
+    if let Some(requested) = config.get("display", "size") {
+        if requested != supported {
+            panic!(
+                "config: [display] size = {} requested, but this build only supports {}",
+                requested, supported
+            );
+        }
+    }
+}
+
+pub type MonoDisplay =
+    Ssd1306<I2CInterface<I2cdev>, DisplaySize128x64, BufferedGraphicsMode<DisplaySize128x64>>;
+
+pub fn build_ssd1306(config: &Ini) -> MonoDisplay {
+    check_size_config(config, "128x64");
+
+    let bus = config
+        .get("display", "bus")
+        .unwrap_or_else(|| "/dev/i2c-0".to_string());
+    let i2c = I2cdev::new::<&Path>(Path::new(bus.as_str())).expect("i2c: failed to open display bus");
+    let interface = I2CDisplayInterface::new(i2c);
+
+    let mut display = Ssd1306::new(interface, DisplaySize128x64, rotation_from_config(config))
+        .into_buffered_graphics_mode();
+    display.init().expect("i2c: failed to init display");
+    display
+}
+
+pub type ColorDisplay = Ili9341<
+    SPIInterface<Spidev, CdevPin, CdevPin>,
+    CdevPin,
+>;
+
+pub fn build_ili9341(config: &Ini) -> ColorDisplay {
+    check_size_config(config, "240x320");
+
+    let bus = config
+        .get("display", "bus")
+        .unwrap_or_else(|| "/dev/spidev0.0".to_string());
+    let spi = Spidev::open(bus.as_str()).unwrap();
+
+    let mut gpiochip = Chip::new("/dev/gpiochip0").unwrap();
+    let dc_line = gpiochip.get_line(24).unwrap();
+    let dc = CdevPin::new(
+        dc_line
+            .request(LineRequestFlags::OUTPUT, 0, "beltpack-dc")
+            .unwrap(),
+    )
+    .unwrap();
+    let rst_line = gpiochip.get_line(25).unwrap();
+    let rst = CdevPin::new(
+        rst_line
+            .request(LineRequestFlags::OUTPUT, 0, "beltpack-rst")
+            .unwrap(),
+    )
+    .unwrap();
+
+    let interface = SPIInterface::new(spi, dc);
+    let display = Ili9341::new(
+        interface,
+        rst,
+        &mut Delay,
+        orientation_from_config(config),
+        DisplaySize240x320,
+    )
+    .unwrap();
+    display
+}
+
+pub fn handle_error<D, C, T, E>(display: &mut D, theme: &Theme<C>, font2: &FontRenderer, result: Result<T, E>)
+where
+    D: DrawTarget<Color = C> + Dimensions,
+    C: PixelColor,
+    E: std::fmt::Display,
+{
+    if let Err(err) = result {
+        let fill = PrimitiveStyleBuilder::new()
+            .stroke_color(theme.fg())
+            .fill_color(theme.fg())
+            .build();
+
+        let banner = Rectangle::new(
+            Point::new(0, 50),
+            Size::new(display.bounding_box().size.width, 14),
+        );
+        banner.into_styled(fill).draw(display).unwrap();
+
+        font2
+            .render_aligned(
+                (String::from("EPRROR: ") + err.to_string().as_str()).as_str(),
+                Point::new(display.bounding_box().center().x, 64),
+                VerticalPosition::Bottom,
+                HorizontalAlignment::Center,
+                FontColor::WithBackground {
+                    fg: theme.bg(),
+                    bg: theme.fg(),
+                },
+                display,
+            )
+            .unwrap();
+    }
+}
+
+/// Draws a full-screen fatal notice: an inverted banner, the short base-6
+/// device code, and a truncated human message. Used from the panic hook,
+/// which only ever has a freshly-opened SSD1306 handle to draw with.
+pub fn draw_fatal_screen(display: &mut MonoDisplay, font2: &FontRenderer, code: &Error, message: &str) {
+    let theme = Theme::mono();
+    display.clear(theme.bg()).ok();
+
+    let inverted = PrimitiveStyleBuilder::new()
+        .stroke_color(theme.fg())
+        .fill_color(theme.fg())
+        .build();
+    Rectangle::new(Point::new(0, 0), Size::new(128, 20))
+        .into_styled(inverted)
+        .draw(display)
+        .ok();
+
+    font2
+        .render_aligned(
+            (String::from("FATAL ") + code.to_string().as_str()).as_str(),
+            Point::new(64, 10),
+            VerticalPosition::Center,
+            HorizontalAlignment::Center,
+            FontColor::WithBackground {
+                fg: theme.bg(),
+                bg: theme.fg(),
+            },
+            display,
+        )
+        .ok();
+
+    let mut truncated = message.replace('\n', " ");
+    truncated.truncate(48);
+    font2
+        .render_aligned(
+            truncated.as_str(),
+            Point::new(64, 30),
+            VerticalPosition::Top,
+            HorizontalAlignment::Center,
+            FontColor::Transparent(theme.fg()),
+            display,
+        )
+        .ok();
+
+    display.flush().ok();
+}
+
+pub fn boot_screen<D, C>(display: &mut D, theme: &Theme<C>, font1_small: &FontRenderer, font2: &FontRenderer)
+where
+    D: DrawTarget<Color = C> + Dimensions,
+    C: PixelColor,
+{
+    const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+    font1_small
+        .render_aligned(
+            "Beltpack\nIntercom",
+            display.bounding_box().center().x_axis() + Point::new(0, 2),
+            VerticalPosition::Top,
+            HorizontalAlignment::Center,
+            FontColor::Transparent(theme.fg()),
+            display,
+        )
+        .unwrap();
+
+    font2
+        .render_aligned(
+            (String::from("SW: ") + VERSION).as_str(),
+            display.bounding_box().center().x_axis() + Point::new(0, 64),
+            VerticalPosition::Bottom,
+            HorizontalAlignment::Center,
+            FontColor::Transparent(theme.fg()),
+            display,
+        )
+        .unwrap();
+}
+
+pub fn name_display<D, C>(
+    display: &mut D,
+    theme: &Theme<C>,
+    font1: &FontRenderer,
+    font2: &FontRenderer,
+    user: &User,
+    talking: bool,
+    presence: Option<bool>,
+) where
+    D: DrawTarget<Color = C> + Dimensions,
+    C: PixelColor,
+{
+    let clear = PrimitiveStyleBuilder::new()
+        .stroke_color(theme.bg())
+        .fill_color(theme.bg())
+        .build();
+
+    Rectangle::new(Point::new(128 - 58, 0), Size::new(58, 14))
+        .into_styled(clear)
+        .draw(display)
+        .unwrap();
+
+    Rectangle::new(Point::new(0, 16), Size::new(128, 33))
+        .into_styled(clear)
+        .draw(display)
+        .unwrap();
+
+    font1
+        .render_aligned(
+            user.to_string().as_str(),
+            display.bounding_box().center() + Point::new(2, 2),
+            VerticalPosition::Center,
+            HorizontalAlignment::Center,
+            FontColor::Transparent(theme.fg()),
+            display,
+        )
+        .unwrap();
+
+    // Presence roster dot: filled for online, hollow for listed-but-offline,
+    // absent entirely when the roster has no entry for this user (roster
+    // disabled, no successful fetch yet, or the user isn't listed).
+    if let Some(online) = presence {
+        let dot = Circle::with_center(display.bounding_box().center() + Point::new(-40, -10), 6);
+        if online {
+            dot.into_styled(PrimitiveStyle::with_fill(theme.fg())).draw(display).ok();
+        } else {
+            dot.into_styled(PrimitiveStyle::with_stroke(theme.fg(), 1))
+                .draw(display)
+                .ok();
+        }
+    }
+
+    if talking {
+        font2
+            .render_aligned(
+                "TALK TO",
+                display.bounding_box().center() + Point::new(64, -30),
+                VerticalPosition::Top,
+                HorizontalAlignment::Right,
+                FontColor::Transparent(theme.fg()),
+                display,
+            )
+            .unwrap();
+    }
+}
+
+pub fn signal_display<D, C>(display: &mut D, theme: &Theme<C>, font2: &FontRenderer, percent: Percent)
+where
+    D: DrawTarget<Color = C> + Dimensions,
+    C: PixelColor,
+{
+    let clear = PrimitiveStyleBuilder::new()
+        .stroke_color(theme.bg())
+        .fill_color(theme.bg())
+        .build();
+
+    Rectangle::new(Point::new(0, 0), Size::new(34, 14))
+        .into_styled(clear)
+        .draw(display)
+        .unwrap();
+
+    font2
+        .render_aligned(
+            (percent.to_string() + "%").as_str(),
+            display.bounding_box().top_left + Point::new(0, 1),
+            VerticalPosition::Top,
+            HorizontalAlignment::Left,
+            FontColor::Transparent(theme.fg()),
+            display,
+        )
+        .unwrap();
+}
+
+pub fn ip_display<D, C>(display: &mut D, theme: &Theme<C>, font2: &FontRenderer, ip: std::net::IpAddr)
+where
+    D: DrawTarget<Color = C> + Dimensions,
+    C: PixelColor,
+{
+    let clear = PrimitiveStyleBuilder::new()
+        .stroke_color(theme.bg())
+        .fill_color(theme.bg())
+        .build();
+
+    Rectangle::new(Point::new(0, 50), Size::new(128, 14))
+        .into_styled(clear)
+        .draw(display)
+        .unwrap();
+
+    font2
+        .render_aligned(
+            ip.to_string().as_str(),
+            Point::new(display.bounding_box().center().x, 64),
+            VerticalPosition::Bottom,
+            HorizontalAlignment::Center,
+            FontColor::Transparent(theme.fg()),
+            display,
+        )
+        .unwrap();
+}
+
+/// Renders up to five history rows, each already formatted by the caller as
+/// `"<relative time> <detail>"`. Reached via a button gesture and paged on
+/// PTT1 (older) / PTT2 (newer); this function only draws one page.
+pub fn history_display<D, C>(display: &mut D, theme: &Theme<C>, font2: &FontRenderer, rows: &[String])
+where
+    D: DrawTarget<Color = C> + Dimensions,
+    C: PixelColor,
+{
+    display.clear(theme.bg()).ok();
+
+    if rows.is_empty() {
+        font2
+            .render_aligned(
+                "No history",
+                Point::new(4, 2),
+                VerticalPosition::Top,
+                HorizontalAlignment::Left,
+                FontColor::Transparent(theme.fg()),
+                display,
+            )
+            .ok();
+        return;
+    }
+
+    for (i, row) in rows.iter().take(5).enumerate() {
+        font2
+            .render_aligned(
+                row.as_str(),
+                Point::new(2, (i as i32) * 13),
+                VerticalPosition::Top,
+                HorizontalAlignment::Left,
+                FontColor::Transparent(theme.fg()),
+                display,
+            )
+            .ok();
+    }
+}
+
+pub fn power_display<D, C>(display: &mut D, theme: &Theme<C>, currenta: &f32, maxa: f32)
+where
+    D: DrawTarget<Color = C> + Dimensions,
+    C: PixelColor,
+{
+    display.clear(theme.bg()).ok();
+    let current = currenta + 1f32;
+    let max = maxa + 1f32;
+
+    let outline = Circle::with_center(display.bounding_box().center(), 48)
+        .into_styled(PrimitiveStyle::with_stroke(theme.fg(), 2));
+    Sector::from_circle(
+        outline.primitive,
+        Angle::from_degrees(0.0),
+        Angle::from_degrees(360.0f32 * (current / max)),
+    )
+    .into_styled(PrimitiveStyle::with_fill(theme.fg()))
+    .draw(display)
+    .unwrap();
+    outline.draw(display).unwrap();
+}
@@ -0,0 +1,143 @@
+use std::os::unix::io::AsRawFd;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gpio_cdev::{Chip, EventRequestFlags, EventType, LineEventHandle, LineRequestFlags};
+use nix::poll::{poll, PollFd, PollFlags};
+
+use configparser::ini::Ini;
+
+use crate::Button;
+
+/// `[input]` config block: debounce window, long-press duration and the
+/// double-tap gap, all in milliseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct InputConfig {
+    pub debounce: Duration,
+    pub long_press: Duration,
+    pub double_tap_window: Duration,
+}
+
+impl InputConfig {
+    pub fn from_config(config: &Ini) -> Self {
+        let debounce_ms = config
+            .getuint("input", "debounce_ms")
+            .unwrap_or(None)
+            .unwrap_or(20);
+        let long_press_ms = config
+            .getuint("input", "long_press_ms")
+            .unwrap_or(None)
+            .unwrap_or(1000);
+        let double_tap_ms = config
+            .getuint("input", "double_tap_ms")
+            .unwrap_or(None)
+            .unwrap_or(350);
+
+        InputConfig {
+            debounce: Duration::from_millis(debounce_ms),
+            long_press: Duration::from_millis(long_press_ms),
+            double_tap_window: Duration::from_millis(double_tap_ms),
+        }
+    }
+}
+
+/// A single button gesture, derived by the watcher thread from the times of
+/// the line's edges. `LongPress` is sent on every 100ms poll tick while the
+/// button stays down, carrying how long it has been held so far, so the main
+/// loop can drive a countdown and compare `held` against the threshold.
+#[derive(Debug, Clone, Copy)]
+pub enum ButtonEvent {
+    Pressed(Button),
+    Released(Button),
+    LongPress { button: Button, held: Duration },
+    DoubleTap(Button),
+}
+
+/// Spawns one watcher thread per `(Button, gpio offset)` pair on `chip_path`
+/// and returns the `mpsc::Receiver` all of them feed. Each thread requests
+/// both-edge line events via `gpio_cdev` and applies the debounce/long-press/
+/// double-tap state machine itself, since every line is independent.
+pub fn spawn(chip_path: &str, lines: Vec<(Button, u32)>, config: InputConfig) -> Receiver<ButtonEvent> {
+    let (tx, rx) = mpsc::channel();
+
+    for (button, offset) in lines {
+        let tx = tx.clone();
+        let chip_path = chip_path.to_string();
+
+        thread::spawn(move || {
+            let mut chip = Chip::new(chip_path.as_str()).expect("gpio: failed to open chip");
+            let line = chip.get_line(offset).expect("gpio: failed to get line");
+            let handle: LineEventHandle = line
+                .events(
+                    LineRequestFlags::INPUT,
+                    EventRequestFlags::BOTH_EDGES,
+                    "beltpack-input",
+                )
+                .expect("gpio: failed to request line events");
+
+            watch_line(button, handle, config, &tx);
+        });
+    }
+
+    rx
+}
+
+fn watch_line(button: Button, handle: LineEventHandle, config: InputConfig, tx: &mpsc::Sender<ButtonEvent>) {
+    let mut last_edge = Instant::now() - config.debounce;
+    let mut pressed_at: Option<Instant> = None;
+    let mut last_release: Option<Instant> = None;
+
+    loop {
+        let mut fds = [PollFd::new(handle.as_raw_fd(), PollFlags::POLLIN)];
+        // While a press is in flight, wake up every 100ms so a held button
+        // keeps producing LongPress ticks for the countdown UI even with no
+        // new edge to read.
+        let timeout_ms = if pressed_at.is_some() { 100 } else { -1 };
+
+        let ready = poll(&mut fds, timeout_ms).unwrap_or(0) > 0;
+
+        if ready {
+            let event = match handle.get_event() {
+                Ok(event) => event,
+                Err(_) => break,
+            };
+
+            let now = Instant::now();
+            if now.duration_since(last_edge) < config.debounce {
+                continue;
+            }
+            last_edge = now;
+
+            match event.event_type() {
+                EventType::RisingEdge => {
+                    pressed_at = Some(now);
+                    tx.send(ButtonEvent::Pressed(button)).ok();
+                }
+                EventType::FallingEdge => {
+                    if let Some(start) = pressed_at.take() {
+                        let held = now.duration_since(start);
+                        // A long hold isn't the first half of a double-tap,
+                        // and its release doesn't count as one either —
+                        // otherwise a short tap shortly after a long press
+                        // could be misread as the long press's pair.
+                        if held < config.long_press {
+                            if let Some(last) = last_release {
+                                if now.duration_since(last) <= config.double_tap_window {
+                                    tx.send(ButtonEvent::DoubleTap(button)).ok();
+                                }
+                            }
+                            last_release = Some(now);
+                        }
+                    }
+                    tx.send(ButtonEvent::Released(button)).ok();
+                }
+            }
+        } else if let Some(start) = pressed_at {
+            let held = start.elapsed();
+            if tx.send(ButtonEvent::LongPress { button, held }).is_err() {
+                break;
+            }
+        }
+    }
+}
@@ -1,37 +1,38 @@
 use core::fmt;
 use std::{
-    net::IpAddr,
-    path::{self, Path},
     process::Command,
+    sync::mpsc::{Receiver, RecvTimeoutError},
     thread,
     time::Duration,
 };
 
-use embedded_graphics::{
-    pixelcolor::BinaryColor,
-    prelude::*,
-    primitives::{Circle, PrimitiveStyle, PrimitiveStyleBuilder, Sector},
-};
-use embedded_graphics_core::primitives::Rectangle;
-use gpio_cdev::{Chip, LineRequestFlags};
-use linux_embedded_hal::{CdevPin, I2cdev};
-use ssd1306::{
-    mode::BufferedGraphicsMode,
-    prelude::{DisplayConfig, I2CInterface},
-    rotation::DisplayRotation,
-    size::{DisplaySize, DisplaySize128x64},
-    I2CDisplayInterface, Ssd1306,
-};
-use u8g2_fonts::{
-    fonts,
-    types::{FontColor, HorizontalAlignment, VerticalPosition},
-    FontRenderer,
-};
+use embedded_graphics::prelude::*;
 
 use local_ip_address::local_ip;
 
 use configparser::ini::Ini;
 
+mod compositor;
+mod diagnostics;
+mod display;
+mod input;
+mod roster;
+mod storage;
+
+use compositor::Compositor;
+use display::{
+    boot_screen, build_ili9341, build_ssd1306, history_display, DisplayBackend, FlushableDisplay,
+    Theme,
+};
+use input::ButtonEvent;
+use roster::{Roster, RosterConfig};
+use storage::{EventKind, EventLog};
+
+const DB_PATH: &str = "beltpack.db";
+const HISTORY_PAGE: u32 = 5;
+const LOW_SIGNAL_THRESHOLD: u8 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Button {
     Power,
     Ptt1,
@@ -50,7 +51,8 @@ impl fmt::Display for Button {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(try_from = "String")]
 struct User(String);
 
 impl User {
@@ -63,13 +65,21 @@ impl User {
     }
 }
 
+impl TryFrom<String> for User {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        User::new(value.as_str())
+    }
+}
+
 impl fmt::Display for User {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Percent(u8);
 
 impl Percent {
@@ -127,526 +137,372 @@ impl fmt::Display for Error {
     }
 }
 
-fn handle_error<T, E>(
-    result: Result<T, E>,
-    display: &mut Ssd1306<
-        I2CInterface<I2cdev>,
-        DisplaySize128x64,
-        BufferedGraphicsMode<DisplaySize128x64>,
-    >,
-    font2: &FontRenderer,
-) where
-    E: std::fmt::Display,
-{
-    match result {
-        Ok(_) => {}
-        Err(err) => {
-            let fill = PrimitiveStyleBuilder::new()
-                .stroke_color(BinaryColor::On)
-                .fill_color(BinaryColor::On)
-                .build();
-
-            Rectangle::new(Point::new(0, 50), Size::new(128, 14))
-                .into_styled(fill)
-                .draw(&mut *display)
-                .unwrap();
-
-            font2
-                .render_aligned(
-                    (String::from("EPRROR: ") + err.to_string().as_str()).as_str(),
-                    Point::new(display.bounding_box().center().x, 64),
-                    VerticalPosition::Bottom,
-                    HorizontalAlignment::Center,
-                    FontColor::WithBackground {
-                        fg: BinaryColor::Off,
-                        bg: BinaryColor::On,
-                    },
-                    &mut *display,
-                )
-                .unwrap();
+fn main() -> Result<(), core::convert::Infallible> {
+    diagnostics::install();
+
+    let mut config = Ini::new();
+    config.load("config.ini").expect("config: failed to load config.ini");
+    let current_user: User = User::new(
+        config
+            .get("config", "device_name")
+            .expect("config: missing [config] device_name")
+            .as_str(),
+    )
+    .unwrap();
+    let target_user_1: User = User::new(
+        config
+            .get("config", "target_1")
+            .expect("config: missing [config] target_1")
+            .as_str(),
+    )
+    .unwrap();
+    let target_user_2: User = User::new(
+        config
+            .get("config", "target_2")
+            .expect("config: missing [config] target_2")
+            .as_str(),
+    )
+    .unwrap();
+
+    let input_config = input::InputConfig::from_config(&config);
+    // TODO: Please change these to use the correct GPIO lines
+    let events = input::spawn(
+        "/dev/gpiochip0",
+        vec![(Button::Power, 17), (Button::Ptt1, 18), (Button::Ptt2, 19)],
+        input_config,
+    );
+
+    let users = Users {
+        current: current_user,
+        target_1: target_user_1,
+        target_2: target_user_2,
+    };
+
+    let event_log = EventLog::open(DB_PATH).unwrap();
+
+    let roster = match RosterConfig::from_config(&config) {
+        Some(roster_config) => Roster::spawn(roster_config, Duration::from_secs(1)),
+        None => Roster::disabled(),
+    };
+
+    match DisplayBackend::from_config(&config).unwrap_or_else(|err| panic!("config: {}", err)) {
+        DisplayBackend::I2cSsd1306 => {
+            let mut display = build_ssd1306(&config);
+            run(&mut display, Theme::mono(), users, events, input_config, event_log, roster)
+        }
+        DisplayBackend::SpiIli9341 => {
+            let mut display = build_ili9341(&config);
+            run(&mut display, Theme::rgb565(), users, events, input_config, event_log, roster)
         }
     }
 }
 
-fn main() -> Result<(), core::convert::Infallible> {
-    let i2c = I2cdev::new::<&Path>(Path::new("/dev/i2c-0").as_ref()).unwrap();
-    let interface = I2CDisplayInterface::new(i2c);
+/// Whether the screen is showing the normal intercom UI or the paged event
+/// history, reached by double-tapping Power.
+enum Mode {
+    Normal,
+    History { offset: u32 },
+}
 
-    let mut config = Ini::new();
-    config.load("config.ini").unwrap();
-    let current_user: User =
-        User::new(config.get("config", "device_name").unwrap().as_str()).unwrap();
-    let target_user_1: User =
-        User::new(config.get("config", "target_1").unwrap().as_str()).unwrap();
-    let target_user_2: User =
-        User::new(config.get("config", "target_2").unwrap().as_str()).unwrap();
-
-    //let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(128, 64));
-    let mut display = Ssd1306::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
-        .into_buffered_graphics_mode();
-    display.init().unwrap();
-
-    let font1 = FontRenderer::new::<fonts::u8g2_font_inr24_mr>();
-    let font1_small = FontRenderer::new::<fonts::u8g2_font_inb16_mr>();
-    let font2 = FontRenderer::new::<fonts::u8g2_font_8x13_mr>();
+struct Users {
+    current: User,
+    target_1: User,
+    target_2: User,
+}
 
-    // TODO: Please change these to use the correct GPIO lines
-    let mut gpiochip = Chip::new("/dev/gpiochip0").unwrap();
-    let power_gpio = gpiochip.get_line(17).unwrap();
-    let ptt1_gpio = gpiochip.get_line(18).unwrap();
-    let ptt2_gpio = gpiochip.get_line(19).unwrap();
-
-    let power_handle = power_gpio
-        .request(LineRequestFlags::INPUT, 1, "my-gpio")
-        .unwrap();
-    let ptt1_handle = ptt1_gpio
-        .request(LineRequestFlags::INPUT, 1, "my-gpio")
-        .unwrap();
-    let ptt2_handle = ptt2_gpio
-        .request(LineRequestFlags::INPUT, 1, "my-gpio")
-        .unwrap();
-
-    boot_screen(&mut display, &font1_small, &font2);
-    display.flush().unwrap();
+fn run<D, C>(
+    display: &mut D,
+    theme: Theme<C>,
+    users: Users,
+    events: Receiver<ButtonEvent>,
+    input_config: input::InputConfig,
+    event_log: EventLog,
+    roster: Roster,
+) -> Result<(), core::convert::Infallible>
+where
+    D: FlushableDisplay<Color = C> + Dimensions,
+    C: embedded_graphics::pixelcolor::PixelColor,
+{
+    let font1 = u8g2_fonts::FontRenderer::new::<u8g2_fonts::fonts::u8g2_font_inr24_mr>();
+    let font1_small = u8g2_fonts::FontRenderer::new::<u8g2_fonts::fonts::u8g2_font_inb16_mr>();
+    let font2 = u8g2_fonts::FontRenderer::new::<u8g2_fonts::fonts::u8g2_font_8x13_mr>();
+
+    boot_screen(display, &theme, &font1_small, &font2);
+    display.flush_display().ok();
 
     thread::sleep(Duration::from_secs(3));
-    display.clear(BinaryColor::Off).unwrap();
+    display.clear(theme.bg()).unwrap();
 
-    let local_ip_addr = local_ip().unwrap();
+    let mut last_ip = local_ip().expect("local_ip: lookup failed");
 
     let p = Command::new("sh")
         .arg("-c")
         .arg("nmcli dev wifi list | awk '/\\*/{if (NR!=1) {print $8}}'")
         .output()
-        .expect("failed to exectue po");
-
-    let percent: Percent = Percent::new(String::from_utf8_lossy(&p.stdout).trim_end()).unwrap();
-
-    signal_display(&mut display, &font2, percent);
-
-    name_display(&mut display, &font1, &font2, &target_user_1, false);
-
-    ip_display(&mut display, &font2, local_ip_addr);
-    display.flush().unwrap();
+        .expect("nmcli: failed to execute");
+
+    let mut last_percent =
+        Percent::new(String::from_utf8_lossy(&p.stdout).trim_end()).unwrap().0;
+
+    let mut compositor = Compositor::new();
+
+    compositor.signal(display, &theme, &font2, Percent(last_percent));
+    compositor.name(
+        display,
+        &theme,
+        &font1,
+        &font2,
+        &users.target_1,
+        false,
+        roster.status_for(&users.target_1),
+    );
+    compositor.ip(display, &theme, &font2, last_ip);
+    compositor.flush(display);
 
     let mut counter = 0;
-    let mut secs: u8 = 0;
+    let mut locked: Option<Button> = None;
+    let mut momentary: Option<Button> = None;
+    let mut mode = Mode::Normal;
 
     loop {
-        if power_handle.get_value().unwrap() == 1 {
-            power_display(&mut display, &secs.into(), 9f32);
-            display.flush().unwrap();
-            if secs >= 10 {
-                break;
+        match events.recv_timeout(Duration::from_millis(100)) {
+            Ok(ButtonEvent::DoubleTap(Button::Power)) => {
+                mode = match mode {
+                    Mode::Normal => Mode::History { offset: 0 },
+                    Mode::History { .. } => Mode::Normal,
+                };
+                redraw_mode(
+                    display, &theme, &font1, &font2, &users, locked, &mode, &event_log, &roster,
+                    &mut compositor, last_percent, last_ip,
+                );
             }
-            secs += 1;
-            thread::sleep(Duration::from_millis(100));
-            continue;
-        } else if secs != 0 {
-            secs = 0;
-            display.clear(BinaryColor::Off).unwrap();
-            counter = 10;
-        }
-        if ptt1_handle.get_value().unwrap() == 1 {
-            name_display(&mut display, &font1, &font2, &target_user_1, true);
-            display.flush().unwrap();
-        } else if ptt2_handle.get_value().unwrap() == 1 {
-            name_display(&mut display, &font1, &font2, &target_user_2, true);
-            display.flush().unwrap();
-        } else {
-            name_display(&mut display, &font1, &font2, &current_user, false);
-            display.flush().unwrap();
+            Ok(ButtonEvent::Pressed(Button::Ptt1)) if matches!(mode, Mode::History { .. }) => {
+                if let Mode::History { offset } = &mut mode {
+                    *offset += HISTORY_PAGE;
+                }
+                redraw_mode(
+                    display, &theme, &font1, &font2, &users, locked, &mode, &event_log, &roster,
+                    &mut compositor, last_percent, last_ip,
+                );
+            }
+            Ok(ButtonEvent::Pressed(Button::Ptt2)) if matches!(mode, Mode::History { .. }) => {
+                if let Mode::History { offset } = &mut mode {
+                    *offset = offset.saturating_sub(HISTORY_PAGE);
+                }
+                redraw_mode(
+                    display, &theme, &font1, &font2, &users, locked, &mode, &event_log, &roster,
+                    &mut compositor, last_percent, last_ip,
+                );
+            }
+            Ok(ButtonEvent::Pressed(Button::Ptt1)) => {
+                momentary = Some(Button::Ptt1);
+                event_log
+                    .record(EventKind::PttActivated, users.target_1.to_string().as_str())
+                    .ok();
+                compositor.name(
+                    display,
+                    &theme,
+                    &font1,
+                    &font2,
+                    &users.target_1,
+                    true,
+                    roster.status_for(&users.target_1),
+                );
+            }
+            Ok(ButtonEvent::Pressed(Button::Ptt2)) => {
+                momentary = Some(Button::Ptt2);
+                event_log
+                    .record(EventKind::PttActivated, users.target_2.to_string().as_str())
+                    .ok();
+                compositor.name(
+                    display,
+                    &theme,
+                    &font1,
+                    &font2,
+                    &users.target_2,
+                    true,
+                    roster.status_for(&users.target_2),
+                );
+            }
+            Ok(ButtonEvent::Released(button)) if button == Button::Ptt1 || button == Button::Ptt2 => {
+                if momentary == Some(button) {
+                    momentary = None;
+                }
+                if locked.is_none() {
+                    compositor.name(display, &theme, &font1, &font2, &users.current, false, None);
+                }
+            }
+            Ok(ButtonEvent::Released(Button::Power)) if matches!(mode, Mode::Normal) => {
+                display.clear(theme.bg()).unwrap();
+                compositor.invalidate();
+                compositor.name(display, &theme, &font1, &font2, &users.current, false, None);
+                counter = 10;
+            }
+            Ok(ButtonEvent::DoubleTap(button)) if button == Button::Ptt1 || button == Button::Ptt2 => {
+                let was_locked = locked;
+                locked = if locked == Some(button) { None } else { Some(button) };
+                if locked != was_locked {
+                    let target = match locked {
+                        Some(Button::Ptt1) => users.target_1.to_string(),
+                        Some(Button::Ptt2) => users.target_2.to_string(),
+                        _ => users.current.to_string(),
+                    };
+                    event_log.record(EventKind::TargetSwitch, target.as_str()).ok();
+                }
+                match locked {
+                    Some(Button::Ptt1) => compositor.name(
+                        display,
+                        &theme,
+                        &font1,
+                        &font2,
+                        &users.target_1,
+                        true,
+                        roster.status_for(&users.target_1),
+                    ),
+                    Some(Button::Ptt2) => compositor.name(
+                        display,
+                        &theme,
+                        &font1,
+                        &font2,
+                        &users.target_2,
+                        true,
+                        roster.status_for(&users.target_2),
+                    ),
+                    _ => compositor.name(display, &theme, &font1, &font2, &users.current, false, None),
+                }
+            }
+            Ok(ButtonEvent::LongPress {
+                button: Button::Power,
+                held,
+            }) => {
+                compositor.power(display, &theme, held, input_config.long_press);
+                if held >= input_config.long_press {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
         }
 
-        if counter == 10 || secs != 0 {
+        if counter >= 10 && matches!(mode, Mode::Normal) {
             let p = Command::new("sh")
                 .arg("-c")
                 .arg("nmcli dev wifi list | awk '/\\*/{if (NR!=1) {print $8}}'")
                 .output()
-                .expect("failed to exectue po");
-
-            let percent: Percent =
-                Percent::new(String::from_utf8_lossy(&p.stdout).trim_end()).unwrap();
+                .expect("nmcli: failed to execute");
+
+            let percent =
+                Percent::new(String::from_utf8_lossy(&p.stdout).trim_end()).unwrap().0;
+            if percent < LOW_SIGNAL_THRESHOLD && last_percent >= LOW_SIGNAL_THRESHOLD {
+                event_log
+                    .record(EventKind::SignalDrop, format!("{}%", percent).as_str())
+                    .ok();
+            }
+            last_percent = percent;
 
-            signal_display(&mut display, &font2, percent);
-            let local_ip_addr = local_ip().unwrap();
-            ip_display(&mut display, &font2, local_ip_addr);
+            let ip = local_ip().expect("local_ip: lookup failed");
+            if ip != last_ip {
+                event_log.record(EventKind::IpChange, ip.to_string().as_str()).ok();
+                last_ip = ip;
+            }
 
-            display.flush().unwrap();
+            compositor.signal(display, &theme, &font2, Percent(last_percent));
+            compositor.ip(display, &theme, &font2, last_ip);
+
+            // Keep a locked target's presence dot live across the roster's
+            // background poll, not just frozen at whatever it was when the
+            // lock was set.
+            match locked {
+                Some(Button::Ptt1) => compositor.name(
+                    display,
+                    &theme,
+                    &font1,
+                    &font2,
+                    &users.target_1,
+                    true,
+                    roster.status_for(&users.target_1),
+                ),
+                Some(Button::Ptt2) => compositor.name(
+                    display,
+                    &theme,
+                    &font1,
+                    &font2,
+                    &users.target_2,
+                    true,
+                    roster.status_for(&users.target_2),
+                ),
+                _ => compositor.name(display, &theme, &font1, &font2, &users.current, false, None),
+            };
             counter = 0;
         }
 
+        compositor.flush(display);
         counter += 1;
-
-        thread::sleep(Duration::from_millis(100));
     }
     Ok(())
 }
 
-fn boot_screen(
-    display: &mut Ssd1306<
-        I2CInterface<I2cdev>,
-        DisplaySize128x64,
-        BufferedGraphicsMode<DisplaySize128x64>,
-    >,
-    font1_small: &FontRenderer,
-    font2: &FontRenderer,
-) {
-    const VERSION: &str = env!("CARGO_PKG_VERSION");
-
-    font1_small
-        .render_aligned(
-            "Beltpack\nIntercom",
-            display.bounding_box().center().x_axis() + Point::new(0, 2),
-            VerticalPosition::Top,
-            HorizontalAlignment::Center,
-            FontColor::Transparent(BinaryColor::On),
-            &mut *display,
-        )
-        .unwrap();
-
-    font2
-        .render_aligned(
-            (String::from("SW: ") + VERSION).as_str(),
-            display.bounding_box().center().x_axis() + Point::new(0, 64),
-            VerticalPosition::Bottom,
-            HorizontalAlignment::Center,
-            FontColor::Transparent(BinaryColor::On),
-            &mut *display,
-        )
-        .unwrap();
-}
-
-fn name_display(
-    display: &mut Ssd1306<
-        I2CInterface<I2cdev>,
-        DisplaySize128x64,
-        BufferedGraphicsMode<DisplaySize128x64>,
-    >,
-    font1: &FontRenderer,
-    font2: &FontRenderer,
-    user: &User,
-    talking: bool,
-) {
-    let clear = PrimitiveStyleBuilder::new()
-        .stroke_color(BinaryColor::Off)
-        .fill_color(BinaryColor::Off)
-        .build();
-
-    Rectangle::new(Point::new(128 - 58, 0), Size::new(58, 14))
-        .into_styled(clear)
-        .draw(&mut *display)
-        .unwrap();
-
-    Rectangle::new(Point::new(0, 16), Size::new(128, 33))
-        .into_styled(clear)
-        .draw(&mut *display)
-        .unwrap();
-
-    font1
-        .render_aligned(
-            user.to_string().as_str(),
-            display.bounding_box().center() + Point::new(2, 2),
-            VerticalPosition::Center,
-            HorizontalAlignment::Center,
-            FontColor::Transparent(BinaryColor::On),
-            &mut *display,
-        )
-        .unwrap();
-
-    if talking {
-        font2
-            .render_aligned(
-                "TALK TO",
-                display.bounding_box().center() + Point::new(64, -30),
-                VerticalPosition::Top,
-                HorizontalAlignment::Right,
-                FontColor::Transparent(BinaryColor::On),
-                &mut *display,
-            )
-            .unwrap();
+fn redraw_mode<D, C>(
+    display: &mut D,
+    theme: &Theme<C>,
+    font1: &u8g2_fonts::FontRenderer,
+    font2: &u8g2_fonts::FontRenderer,
+    users: &Users,
+    locked: Option<Button>,
+    mode: &Mode,
+    event_log: &EventLog,
+    roster: &Roster,
+    compositor: &mut Compositor,
+    last_percent: u8,
+    last_ip: std::net::IpAddr,
+) where
+    D: FlushableDisplay<Color = C> + Dimensions,
+    C: embedded_graphics::pixelcolor::PixelColor,
+{
+    match mode {
+        Mode::Normal => {
+            match locked {
+                Some(Button::Ptt1) => compositor.name(
+                    display,
+                    theme,
+                    font1,
+                    font2,
+                    &users.target_1,
+                    true,
+                    roster.status_for(&users.target_1),
+                ),
+                Some(Button::Ptt2) => compositor.name(
+                    display,
+                    theme,
+                    font1,
+                    font2,
+                    &users.target_2,
+                    true,
+                    roster.status_for(&users.target_2),
+                ),
+                _ => compositor.name(display, theme, font1, font2, &users.current, false, None),
+            }
+            // History mode clears the whole screen, so the signal/IP rows
+            // it blanked out need to come back explicitly here rather than
+            // waiting on the periodic refresh in the main loop.
+            compositor.signal(display, theme, font2, Percent(last_percent));
+            compositor.ip(display, theme, font2, last_ip);
+        }
+        Mode::History { offset } => {
+            let rows = event_log
+                .recent(HISTORY_PAGE, *offset)
+                .unwrap_or_default()
+                .iter()
+                .map(|event| format!("{} {}", storage::relative_time(event.at), event.detail))
+                .collect::<Vec<_>>();
+            history_display(display, theme, font2, rows.as_slice());
+            // History draws the whole screen directly, bypassing the
+            // compositor's per-region cache, so the next return to Normal
+            // must not think signal/name/ip are still showing what they
+            // were before History took over.
+            display.flush_display().ok();
+            compositor.invalidate();
+        }
     }
 }
-
-fn signal_display(
-    display: &mut Ssd1306<
-        I2CInterface<I2cdev>,
-        DisplaySize128x64,
-        BufferedGraphicsMode<DisplaySize128x64>,
-    >,
-    font2: &FontRenderer,
-    percent: Percent,
-) {
-    let clear = PrimitiveStyleBuilder::new()
-        .stroke_color(BinaryColor::Off)
-        .fill_color(BinaryColor::Off)
-        .build();
-
-    Rectangle::new(Point::new(0, 0), Size::new(34, 14))
-        .into_styled(clear)
-        .draw(&mut *display)
-        .unwrap();
-
-    font2
-        .render_aligned(
-            (percent.to_string() + "%").as_str(),
-            display.bounding_box().top_left + Point::new(0, 1),
-            VerticalPosition::Top,
-            HorizontalAlignment::Left,
-            FontColor::Transparent(BinaryColor::On),
-            &mut *display,
-        )
-        .unwrap();
-}
-
-fn ip_display(
-    display: &mut Ssd1306<
-        I2CInterface<I2cdev>,
-        DisplaySize128x64,
-        BufferedGraphicsMode<DisplaySize128x64>,
-    >,
-    font2: &FontRenderer,
-    ip: IpAddr,
-) {
-    let clear = PrimitiveStyleBuilder::new()
-        .stroke_color(BinaryColor::Off)
-        .fill_color(BinaryColor::Off)
-        .build();
-
-    Rectangle::new(Point::new(0, 50), Size::new(128, 14))
-        .into_styled(clear)
-        .draw(&mut *display)
-        .unwrap();
-
-    font2
-        .render_aligned(
-            ip.to_string().as_str(),
-            Point::new(display.bounding_box().center().x, 64),
-            VerticalPosition::Bottom,
-            HorizontalAlignment::Center,
-            FontColor::Transparent(BinaryColor::On),
-            &mut *display,
-        )
-        .unwrap();
-}
-
-fn power_display(
-    display: &mut Ssd1306<
-        I2CInterface<I2cdev>,
-        DisplaySize128x64,
-        BufferedGraphicsMode<DisplaySize128x64>,
-    >,
-    currenta: &f32,
-    maxa: f32,
-) {
-    display.clear(BinaryColor::Off).unwrap();
-    let current = currenta + 1f32;
-    let max = maxa + 1f32;
-
-    let outline = Circle::with_center(display.bounding_box().center(), 48)
-        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2));
-    Sector::from_circle(
-        outline.primitive,
-        Angle::from_degrees(0.0),
-        Angle::from_degrees(360.0f32 * (current / max)),
-    )
-    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
-    .draw(&mut *display)
-    .unwrap();
-    outline.draw(&mut *display).unwrap();
-}
-
-// fn test(
-//     display: &mut Ssd1306<
-//     I2CInterface<I2cdev>,
-//     DisplaySize128x64,
-//     BufferedGraphicsMode<DisplaySize128x64>,
-//     >,
-//     font1_small: &FontRenderer,
-//     font2: &FontRenderer,
-//     ) -> (Button, Button, Button) {
-//     display.clear(BinaryColor::Off).unwrap();
-//     let mut power = Button::Unknown;
-//     let mut ptt1 = Button::Unknown;
-//     let mut ptt2 = Button::Unknown;
-//     let mut secs = 0;
-//     font1_small
-//         .render_aligned(
-//             "TEST",
-//             display.bounding_box().center(),
-//             VerticalPosition::Center,
-//             HorizontalAlignment::Center,
-//             FontColor::Transparent(BinaryColor::On),
-//             &mut *display,
-//             )
-//         .unwrap();
-//     display.flush().unwrap();
-//     thread::sleep(Duration::from_secs(1));
-//     let device_state = DeviceState::new();
-//     display.clear(BinaryColor::Off).unwrap();
-//     font1_small
-//         .render_aligned(
-//             "POWER",
-//             display.bounding_box().center(),
-//             VerticalPosition::Center,
-//             HorizontalAlignment::Center,
-//             FontColor::Transparent(BinaryColor::On),
-//             &mut *display,
-//             )
-//         .unwrap();
-//     display.flush().unwrap();
-//     loop {
-//         let keys: Vec<Keycode> = device_state.get_keys();
-//
-//         if keys.contains(&Keycode::Escape) {
-//             power = Button::Power;
-//         } else if keys.contains(&Keycode::Left) {
-//             power = Button::Ptt1;
-//         } else if keys.contains(&Keycode::Right) {
-//             power = Button::Ptt2;
-//         }
-//
-//         if !matches!(power, Button::Unknown)
-//             && !keys.contains(&Keycode::Escape)
-//                 && !keys.contains(&Keycode::Left)
-//                 && !keys.contains(&Keycode::Right)
-//                 {
-//                     break;
-//                 }
-//
-//         if secs >= 50 {
-//             break;
-//         }
-//
-//         thread::sleep(Duration::from_millis(100));
-//         secs += 1;
-//     }
-//     thread::sleep(Duration::from_secs(1));
-//     secs = 0;
-//     display.clear(BinaryColor::Off).unwrap();
-//     font1_small
-//         .render_aligned(
-//             "PTT 1",
-//             display.bounding_box().center(),
-//             VerticalPosition::Center,
-//             HorizontalAlignment::Center,
-//             FontColor::Transparent(BinaryColor::On),
-//             &mut *display,
-//             )
-//         .unwrap();
-//     display.flush().unwrap();
-//     loop {
-//         let keys: Vec<Keycode> = device_state.get_keys();
-//
-//         if keys.contains(&Keycode::Left) {
-//             ptt1 = Button::Ptt1;
-//         } else if keys.contains(&Keycode::Escape) {
-//             ptt1 = Button::Power;
-//         } else if keys.contains(&Keycode::Right) {
-//             ptt1 = Button::Ptt2;
-//         }
-//
-//         if !matches!(ptt1, Button::Unknown)
-//             && !keys.contains(&Keycode::Escape)
-//                 && !keys.contains(&Keycode::Left)
-//                 && !keys.contains(&Keycode::Right)
-//                 {
-//                     break;
-//                 }
-//         if secs >= 50 {
-//             break;
-//         }
-//
-//         thread::sleep(Duration::from_millis(100));
-//         secs += 1;
-//     }
-//
-//     secs = 0;
-//     display.clear(BinaryColor::Off).unwrap();
-//     font1_small
-//         .render_aligned(
-//             "PTT 2",
-//             display.bounding_box().center(),
-//             VerticalPosition::Center,
-//             HorizontalAlignment::Center,
-//             FontColor::Transparent(BinaryColor::On),
-//             &mut *display,
-//             )
-//         .unwrap();
-//     display.flush().unwrap();
-//     loop {
-//         let keys: Vec<Keycode> = device_state.get_keys();
-//
-//         if keys.contains(&Keycode::Right) {
-//             ptt2 = Button::Ptt2;
-//         } else if keys.contains(&Keycode::Escape) {
-//             ptt2 = Button::Power;
-//         } else if keys.contains(&Keycode::Left) {
-//             ptt2 = Button::Ptt1;
-//         }
-//
-//         if !matches!(ptt2, Button::Unknown)
-//             && !keys.contains(&Keycode::Escape)
-//                 && !keys.contains(&Keycode::Left)
-//                 && !keys.contains(&Keycode::Right)
-//                 {
-//                     break;
-//                 }
-//         if secs >= 50 {
-//             break;
-//         }
-//
-//         thread::sleep(Duration::from_millis(100));
-//         secs += 1;
-//     }
-//
-//     if matches!(power, Button::Power)
-//         && matches!(ptt1, Button::Ptt1)
-//             && matches!(ptt2, Button::Ptt2)
-//             {
-//                 display.clear(BinaryColor::Off).unwrap();
-//                 font1_small
-//                     .render_aligned(
-//                         "ALL GOOD",
-//                         display.bounding_box().center(),
-//                         VerticalPosition::Center,
-//                         HorizontalAlignment::Center,
-//                         FontColor::Transparent(BinaryColor::On),
-//                         &mut *display,
-//                         )
-//                     .unwrap();
-//                 display.flush().unwrap();
-//                 thread::sleep(Duration::from_secs(1));
-//             } else {
-//                 display.clear(BinaryColor::Off).unwrap();
-//                 font1_small
-//                     .render_aligned(
-//                         "ERROR!",
-//                         display.bounding_box().center(),
-//                         VerticalPosition::Center,
-//                         HorizontalAlignment::Center,
-//                         FontColor::Transparent(BinaryColor::On),
-//                         &mut *display,
-//                         )
-//                     .unwrap();
-//                 display.flush().unwrap();
-//                 thread::sleep(Duration::from_secs_f32(0.5));
-//
-//                 display.clear(BinaryColor::Off).unwrap();
-//                 font2
-//                     .render_aligned(
-//                         format!("Power: {}\nPTT1: {}\nPTT2: {}", power, ptt1, ptt2).as_str(),
-//                         display.bounding_box().center(),
-//                         VerticalPosition::Center,
-//                         HorizontalAlignment::Center,
-//                         FontColor::Transparent(BinaryColor::On),
-//                         &mut *display,
-//                         )
-//                     .unwrap();
-//                 display.flush().unwrap();
-//                 thread::sleep(Duration::from_secs(2));
-//                 display.clear(BinaryColor::Off).unwrap();
-//                 display.flush().unwrap();
-//             }
-//     (power, ptt1, ptt2)
-// }
@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use configparser::ini::Ini;
+use serde::Deserialize;
+
+use crate::User;
+
+/// One beltpack as reported by the roster server.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RosterEntry {
+    pub name: User,
+    pub online: bool,
+}
+
+/// `[roster]` config block. `username`/`password`, if both present, are
+/// sent as HTTP basic auth the way raspi-oled talks to its upstream.
+#[derive(Debug, Clone)]
+pub struct RosterConfig {
+    url: String,
+    basic_auth: Option<String>,
+}
+
+impl RosterConfig {
+    pub fn from_config(config: &Ini) -> Option<Self> {
+        let url = config.get("roster", "url")?;
+        let basic_auth = match (config.get("roster", "username"), config.get("roster", "password")) {
+            (Some(user), Some(pass)) => Some(base64::encode(format!("{}:{}", user, pass))),
+            _ => None,
+        };
+        Some(RosterConfig { url, basic_auth })
+    }
+}
+
+/// Last-known presence roster. A background thread refreshes it on the same
+/// cadence as the signal/IP poll and the display loop reads it through a
+/// mutex; when the server is unreachable the last good snapshot just goes
+/// stale rather than blanking out.
+#[derive(Clone)]
+pub struct Roster {
+    entries: Arc<Mutex<Vec<RosterEntry>>>,
+}
+
+impl Roster {
+    /// No `[roster]` section configured: `status_for` always returns `None`,
+    /// so `name_display` falls back to drawing target names with no dot.
+    pub fn disabled() -> Self {
+        Roster {
+            entries: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn spawn(config: RosterConfig, poll_every: Duration) -> Self {
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let shared = entries.clone();
+
+        thread::spawn(move || loop {
+            if let Ok(fetched) = fetch(&config) {
+                *shared.lock().unwrap() = fetched;
+            }
+            thread::sleep(poll_every);
+        });
+
+        Roster { entries }
+    }
+
+    pub fn status_for(&self, user: &User) -> Option<bool> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|entry| entry.name.to_string() == user.to_string())
+            .map(|entry| entry.online)
+    }
+}
+
+fn fetch(config: &RosterConfig) -> Result<Vec<RosterEntry>, String> {
+    let mut request = ureq::get(config.url.as_str());
+    if let Some(basic_auth) = &config.basic_auth {
+        request = request.set("Authorization", format!("Basic {}", basic_auth).as_str());
+    }
+
+    request
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_json::<Vec<RosterEntry>>()
+        .map_err(|err| err.to_string())
+}
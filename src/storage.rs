@@ -0,0 +1,145 @@
+use rusqlite::{params, Connection};
+use time::OffsetDateTime;
+
+const RETENTION_DAYS: i64 = 7;
+
+/// What a logged row represents. Kept as a small enum rather than a free
+/// string so callers can't typo a kind that nothing queries for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    PttActivated,
+    TargetSwitch,
+    SignalDrop,
+    IpChange,
+    FatalError,
+}
+
+impl EventKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::PttActivated => "ptt_activated",
+            EventKind::TargetSwitch => "target_switch",
+            EventKind::SignalDrop => "signal_drop",
+            EventKind::IpChange => "ip_change",
+            EventKind::FatalError => "fatal_error",
+        }
+    }
+
+    fn from_str(value: &str) -> EventKind {
+        match value {
+            "ptt_activated" => EventKind::PttActivated,
+            "target_switch" => EventKind::TargetSwitch,
+            "signal_drop" => EventKind::SignalDrop,
+            "ip_change" => EventKind::IpChange,
+            _ => EventKind::FatalError,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    pub kind: EventKind,
+    pub detail: String,
+    pub at: OffsetDateTime,
+}
+
+/// On-device history of PTT activity, target switches, signal drops, IP
+/// changes and fatal errors, kept in `beltpack.db` so a field tech can
+/// answer "when did I drop signal" without a host machine.
+pub struct EventLog {
+    conn: Connection,
+}
+
+impl EventLog {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        migrate(&conn)?;
+        let log = EventLog { conn };
+        log.vacuum_older_than(RETENTION_DAYS)?;
+        Ok(log)
+    }
+
+    pub fn record(&self, kind: EventKind, detail: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO events (kind, detail, at_unix) VALUES (?1, ?2, ?3)",
+            params![kind.as_str(), detail, OffsetDateTime::now_utc().unix_timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent `limit` events, newest first, `offset` rows in — used
+    /// to page the history screen on PTT1 (older) / PTT2 (newer).
+    pub fn recent(&self, limit: u32, offset: u32) -> rusqlite::Result<Vec<LoggedEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, detail, at_unix FROM events ORDER BY at_unix DESC LIMIT ?1 OFFSET ?2",
+        )?;
+        let rows = stmt.query_map(params![limit, offset], |row| {
+            let kind: String = row.get(0)?;
+            let at_unix: i64 = row.get(2)?;
+            Ok(LoggedEvent {
+                kind: EventKind::from_str(kind.as_str()),
+                detail: row.get(1)?,
+                at: OffsetDateTime::from_unix_timestamp(at_unix)
+                    .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+            })
+        })?;
+        rows.collect()
+    }
+
+    fn vacuum_older_than(&self, days: i64) -> rusqlite::Result<()> {
+        let cutoff = OffsetDateTime::now_utc().unix_timestamp() - days * 86_400;
+        self.conn
+            .execute("DELETE FROM events WHERE at_unix < ?1", params![cutoff])?;
+        self.conn.execute_batch("VACUUM;")
+    }
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            detail TEXT NOT NULL,
+            at_unix INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS events_at_unix ON events (at_unix);",
+    )
+}
+
+/// Best-effort fatal-error logging from the panic hook, which can't reuse a
+/// live `EventLog` handle any more than it can reuse the live display — it
+/// opens its own short-lived connection the same way `diagnostics` reopens
+/// `/dev/i2c-0`.
+pub fn log_fatal(path: &str, code: &str, message: &str) {
+    if let Ok(conn) = Connection::open(path) {
+        if migrate(&conn).is_ok() {
+            let detail = format!("{}: {}", code, message);
+            conn.execute(
+                "INSERT INTO events (kind, detail, at_unix) VALUES (?1, ?2, ?3)",
+                params![
+                    EventKind::FatalError.as_str(),
+                    detail,
+                    OffsetDateTime::now_utc().unix_timestamp()
+                ],
+            )
+            .ok();
+        }
+    }
+}
+
+/// Renders a timestamp the way the history screen wants it: "2m ago",
+/// "3h ago", "yesterday", "5d ago".
+pub fn relative_time(at: OffsetDateTime) -> String {
+    let delta = OffsetDateTime::now_utc() - at;
+    if delta.whole_seconds() < 60 {
+        "just now".to_string()
+    } else if delta.whole_minutes() < 60 {
+        format!("{}m ago", delta.whole_minutes())
+    } else if delta.whole_hours() < 24 {
+        format!("{}h ago", delta.whole_hours())
+    } else if delta.whole_days() == 1 {
+        "yesterday".to_string()
+    } else {
+        format!("{}d ago", delta.whole_days())
+    }
+}